@@ -1,7 +1,9 @@
+pub mod run;
 pub mod start;
 pub mod task;
 pub mod update;
 
+pub use run::handle_run_command;
 pub use start::{StartMode, handle_start_command};
 pub use task::{TaskAction, handle_task_command};
 pub use update::handle_update_command;
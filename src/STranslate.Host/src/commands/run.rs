@@ -0,0 +1,78 @@
+use crate::commands::start::{run_start, StartArgs};
+use crate::commands::task::{run_task, TaskArgs};
+use crate::commands::update::{run_update, UpdateArgs};
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// 单个批处理步骤，`#[serde(tag = "type")]` 使其直接映射到清单文件里的 `Start`/`Update`/`Task` 条目
+///
+/// 注意：不要在 `ManifestStep` 上对本枚举做 `#[serde(flatten)]` —— 内部标签枚举加
+/// `flatten` 的组合在 `toml` 反序列化器下并不稳定，嵌套在 `step` 字段里即可避免该问题。
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum Step {
+    Start(StartArgs),
+    Update(UpdateArgs),
+    Task(TaskArgs),
+}
+
+/// 清单中的一个步骤，`continue_on_error` 为 true 时该步骤失败不会中止后续步骤
+#[derive(Debug, Deserialize)]
+pub struct ManifestStep {
+    pub step: Step,
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+/// `run --manifest` 读取的清单文件，描述一个有序的操作序列
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    pub steps: Vec<ManifestStep>,
+}
+
+pub fn handle_run_command(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let manifest_path = matches.get_one::<String>("manifest").unwrap();
+    let verbose = matches.get_flag("verbose");
+
+    let manifest = load_manifest(manifest_path)?;
+
+    if verbose {
+        println!("📜 加载操作清单: {} ({} 个步骤)", manifest_path, manifest.steps.len());
+    }
+
+    for (index, step) in manifest.steps.iter().enumerate() {
+        if verbose {
+            println!("▶️  执行步骤 {}/{}", index + 1, manifest.steps.len());
+        }
+
+        let result = match &step.step {
+            Step::Start(args) => run_start(args),
+            Step::Update(args) => run_update(args),
+            Step::Task(args) => run_task(args),
+        };
+
+        if let Err(err) = result {
+            if step.continue_on_error {
+                println!("⚠️  步骤 {} 失败，已忽略并继续: {}", index + 1, err);
+                continue;
+            }
+            return Err(format!("步骤 {} 失败，已中止后续步骤: {}", index + 1, err).into());
+        }
+    }
+
+    println!("✅ 清单执行完成!");
+    Ok(())
+}
+
+fn load_manifest(path: &str) -> Result<Manifest, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("json") => Ok(serde_json::from_str(&content)?),
+        Some("toml") => Ok(toml::from_str(&content)?),
+        _ => Err("清单文件必须以 .toml 或 .json 结尾".into()),
+    }
+}
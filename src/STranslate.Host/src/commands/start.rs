@@ -1,10 +1,13 @@
 use clap::{ArgMatches, ValueEnum};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::process::{Command as ProcessCommand, Stdio};
 use std::thread;
 use std::time::Duration;
 
-#[derive(Clone, Debug, ValueEnum)]
+/// `#[serde(rename_all = "kebab-case")]` 使清单文件里的取值与 `ValueEnum` 派生的 CLI 取值一致
+#[derive(Clone, Debug, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum StartMode {
     /// 直接启动进程
     Direct,
@@ -14,22 +17,57 @@ pub enum StartMode {
     Task,
 }
 
+/// `start` 子命令的参数，供 CLI 解析与 `run --manifest` 的批处理步骤共用
+#[derive(Debug, Deserialize)]
+pub struct StartArgs {
+    pub mode: StartMode,
+    pub target: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub delay: u64,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
 pub fn handle_start_command(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let mode = matches.get_one::<StartMode>("mode").unwrap();
     let target = matches.get_one::<String>("target").unwrap();
-    let args: Vec<&String> = matches
+    let args: Vec<String> = matches
         .get_many::<String>("args")
         .unwrap_or_default()
+        .cloned()
         .collect();
     let delay = *matches.get_one::<u64>("delay").unwrap();
     let verbose = matches.get_flag("verbose");
 
+    run_start(&StartArgs {
+        mode: mode.clone(),
+        target: target.clone(),
+        args,
+        delay,
+        verbose,
+    })
+}
+
+pub fn run_start(args: &StartArgs) -> Result<(), Box<dyn Error>> {
+    let StartArgs {
+        mode,
+        target,
+        args: proc_args,
+        delay,
+        verbose,
+    } = args;
+    let delay = *delay;
+    let verbose = *verbose;
+    let proc_args: Vec<&String> = proc_args.iter().collect();
+
     if verbose {
         println!("🚀 准备启动程序...");
         println!("   启动方式: {:?}", mode);
         println!("   目标: {}", target);
-        if !args.is_empty() {
-            println!("   参数: {:?}", args);
+        if !proc_args.is_empty() {
+            println!("   参数: {:?}", proc_args);
         }
         if delay > 0 {
             println!("   延迟: {} 秒", delay);
@@ -45,10 +83,10 @@ pub fn handle_start_command(matches: &ArgMatches) -> Result<(), Box<dyn Error>>
 
     match mode {
         StartMode::Direct => {
-            start_direct_process(target, &args, verbose)?;
+            start_direct_process(target, &proc_args, verbose)?;
         }
         StartMode::Elevated => {
-            start_elevated_process(target, &args, verbose)?;
+            start_elevated_process(target, &proc_args, verbose)?;
         }
         StartMode::Task => {
             start_task_scheduler(target, verbose)?;
@@ -1,11 +1,37 @@
 use chrono::Local;
 use clap::{ArgMatches, ValueEnum};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::fs;
 use std::path::Path;
-use std::process::Command as ProcessCommand;
 
-#[derive(Clone, Debug, ValueEnum)]
+#[cfg(target_os = "windows")]
+use windows::{
+    Win32::Foundation::GetLastError,
+    Win32::Security::{ConvertSidToStringSidW, LookupAccountNameW, SID_NAME_USE},
+    Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+        COINIT_MULTITHREADED, VARIANT,
+    },
+    Win32::System::TaskScheduler::{
+        IBootTrigger, IDailyTrigger, IExecAction, ILogonTrigger, ITaskFolder, ITaskService,
+        TaskScheduler, TASK_ACTION_EXEC, TASK_CREATE_OR_UPDATE, TASK_LOGON_INTERACTIVE_TOKEN,
+        TASK_TRIGGER_BOOT, TASK_TRIGGER_DAILY, TASK_TRIGGER_LOGON,
+    },
+    core::{BSTR, HRESULT, PWSTR},
+};
+
+/// 创建任务时附加的触发器选项，`false`/`None` 表示不附加对应触发器
+#[cfg(target_os = "windows")]
+#[derive(Default)]
+pub struct TriggerOptions {
+    pub logon: bool,
+    pub daily_at: Option<String>,
+    pub boot: bool,
+}
+
+/// `#[serde(rename_all = "kebab-case")]` 使清单文件里的取值与 `ValueEnum` 派生的 CLI 取值一致
+#[derive(Clone, Debug, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum TaskAction {
     /// 检查任务是否存在
     Check,
@@ -17,72 +43,196 @@ pub enum TaskAction {
     List,
 }
 
+/// 描述一个已注册任务的结构化摘要
+#[derive(Debug)]
+pub struct TaskInfo {
+    pub name: String,
+    pub enabled: bool,
+    pub state: String,
+}
+
+/// `task` 子命令的参数，供 CLI 解析与 `run --manifest` 的批处理步骤共用
+#[derive(Debug, Deserialize)]
+pub struct TaskArgs {
+    pub action: TaskAction,
+    pub name: String,
+    pub program: Option<String>,
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_run_level")]
+    pub run_level: String,
+    #[serde(default)]
+    pub force: bool,
+    #[serde(default)]
+    pub trigger_logon: bool,
+    pub trigger_daily: Option<String>,
+    #[serde(default)]
+    pub trigger_boot: bool,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+fn default_run_level() -> String {
+    "limited".to_string()
+}
+
 pub fn handle_task_command(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let action = matches.get_one::<TaskAction>("action").unwrap();
-    let verbose = matches.get_flag("verbose");
+    let args = TaskArgs {
+        action: matches.get_one::<TaskAction>("action").unwrap().clone(),
+        name: matches.get_one::<String>("name").unwrap().clone(),
+        program: matches.get_one::<String>("program").cloned(),
+        working_dir: matches.get_one::<String>("working-dir").cloned(),
+        description: matches.get_one::<String>("description").unwrap().clone(),
+        run_level: matches.get_one::<String>("run-level").unwrap().clone(),
+        force: matches.get_flag("force"),
+        trigger_logon: matches.get_flag("trigger-logon"),
+        trigger_daily: matches.get_one::<String>("trigger-daily").cloned(),
+        trigger_boot: matches.get_flag("trigger-boot"),
+        verbose: matches.get_flag("verbose"),
+    };
+
+    run_task(&args)
+}
+
+pub fn run_task(args: &TaskArgs) -> Result<(), Box<dyn Error>> {
+    let verbose = args.verbose;
 
     #[cfg(target_os = "windows")]
     {
-        match action {
+        match args.action {
             TaskAction::Check => {
-                let task_name = matches.get_one::<String>("name").unwrap();
-                check_task_exists(task_name, verbose)?;
+                let exists = check_task_exists(&args.name, verbose)?;
+                if exists {
+                    println!("✅ 任务计划存在: {}", args.name);
+                } else {
+                    println!("❌ 任务计划不存在: {}", args.name);
+                }
             }
             TaskAction::Create => {
-                let task_name = matches.get_one::<String>("name").unwrap();
-                let program = matches
-                    .get_one::<String>("program")
+                let program = args
+                    .program
+                    .as_ref()
                     .ok_or("创建任务时必须指定程序路径 --program")?;
-                let working_dir = matches.get_one::<String>("working-dir");
-                let description = matches.get_one::<String>("description").unwrap();
-                let run_level = matches.get_one::<String>("run-level").unwrap();
-                let force = matches.get_flag("force");
+                let triggers = TriggerOptions {
+                    logon: args.trigger_logon,
+                    daily_at: args.trigger_daily.clone(),
+                    boot: args.trigger_boot,
+                };
 
                 create_task(
-                    task_name,
+                    &args.name,
                     program,
-                    working_dir,
-                    description,
-                    run_level,
-                    force,
+                    args.working_dir.as_ref(),
+                    &args.description,
+                    &args.run_level,
+                    args.force,
+                    &triggers,
                     verbose,
                 )?;
             }
             TaskAction::Delete => {
-                let task_name = matches.get_one::<String>("name").unwrap();
-                delete_task(task_name, verbose)?;
+                delete_task(&args.name, verbose)?;
             }
             TaskAction::List => {
-                list_tasks(verbose)?;
+                let tasks = list_tasks(verbose)?;
+                println!("📋 任务计划列表:");
+                for task in &tasks {
+                    println!("   {} [{}] - {}", task.name, task.state, task.enabled);
+                }
             }
         }
     }
 
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = &args.name;
+    }
+
     Ok(())
 }
 
+/// 建立到本机 Task Scheduler 服务的连接并获取根任务文件夹（`\`）
+#[cfg(target_os = "windows")]
+fn connect_task_service() -> Result<(ITaskService, ITaskFolder), Box<dyn Error>> {
+    unsafe {
+        let service: ITaskService = CoCreateInstance(&TaskScheduler, None, CLSCTX_INPROC_SERVER)?;
+        service.Connect(
+            &VARIANT::default(),
+            &VARIANT::default(),
+            &VARIANT::default(),
+            &VARIANT::default(),
+        )?;
+
+        let folder = service.GetFolder(&BSTR::from("\\"))?;
+
+        Ok((service, folder))
+    }
+}
+
+/// 初始化 COM 库，调用方负责在作用域结束时保持其存活直到操作完成
 #[cfg(target_os = "windows")]
-fn check_task_exists(task_name: &str, verbose: bool) -> Result<(), Box<dyn Error>> {
+struct ComGuard;
+
+#[cfg(target_os = "windows")]
+impl ComGuard {
+    fn new() -> Result<Self, Box<dyn Error>> {
+        unsafe {
+            CoInitializeEx(None, COINIT_MULTITHREADED).ok()?;
+        }
+        Ok(Self)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        unsafe {
+            CoUninitialize();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn check_task_exists(task_name: &str, verbose: bool) -> Result<bool, Box<dyn Error>> {
     if verbose {
         println!("🔍 检查任务计划是否存在: {}", task_name);
     }
 
-    let output = ProcessCommand::new("schtasks")
-        .args(&["/Query", "/TN", task_name])
-        .output()?;
+    let _com = ComGuard::new()?;
+    let (_service, folder) = connect_task_service()?;
 
-    if output.status.success() {
-        println!("✅ 任务计划存在: {}", task_name);
-        if verbose {
-            let info = String::from_utf8_lossy(&output.stdout);
-            println!("📋 任务信息:");
-            println!("{}", info);
+    task_exists_in_folder(&folder, task_name, verbose)
+}
+
+/// 在已持有的任务文件夹里查询任务是否存在，供已经建立连接的调用方复用，避免重复建立
+/// COM 连接
+#[cfg(target_os = "windows")]
+fn task_exists_in_folder(
+    folder: &ITaskFolder,
+    task_name: &str,
+    verbose: bool,
+) -> Result<bool, Box<dyn Error>> {
+    unsafe {
+        match folder.GetTask(&BSTR::from(task_name)) {
+            Ok(task) => {
+                if verbose {
+                    let state = task.State()?;
+                    println!("📋 任务状态: {:?}", state);
+                }
+                Ok(true)
+            }
+            Err(err) => {
+                // 0x80070002 (ERROR_FILE_NOT_FOUND) 表示任务不存在，其它 HRESULT 视为真实错误
+                const ERROR_FILE_NOT_FOUND: HRESULT = HRESULT(0x80070002u32 as i32);
+                if err.code() == ERROR_FILE_NOT_FOUND {
+                    Ok(false)
+                } else {
+                    Err(format!("查询任务计划失败: {}", err).into())
+                }
+            }
         }
-    } else {
-        println!("❌ 任务计划不存在: {}", task_name);
     }
-
-    Ok(())
 }
 
 #[cfg(target_os = "windows")]
@@ -93,6 +243,7 @@ fn create_task(
     description: &str,
     run_level: &str,
     force: bool,
+    triggers: &TriggerOptions,
     verbose: bool,
 ) -> Result<(), Box<dyn Error>> {
     if verbose {
@@ -118,178 +269,216 @@ fn create_task(
             .to_string()
     };
 
-    let check_output = ProcessCommand::new("schtasks")
-        .args(&["/Query", "/TN", task_name])
-        .output()?;
+    let _com = ComGuard::new()?;
+    let (service, folder) = connect_task_service()?;
 
-    if check_output.status.success() && !force {
+    if task_exists_in_folder(&folder, task_name, false)? && !force {
         println!("✅ 任务计划已存在: {}，使用 --force 强制覆盖", task_name);
         return Ok(());
     }
 
-    let xml_content = generate_task_xml(task_name, program, &work_dir, description, run_level)?;
+    unsafe {
+        let definition = service.NewTask(0)?;
+
+        let registration_info = definition.RegistrationInfo()?;
+        registration_info.SetAuthor(&BSTR::from("stranslate - zggsong"))?;
+        registration_info.SetDescription(&BSTR::from(description))?;
+        registration_info.SetURI(&BSTR::from(format!("\\{}", task_name)))?;
+
+        let principal = definition.Principal()?;
+        principal.SetLogonType(TASK_LOGON_INTERACTIVE_TOKEN)?;
+        let run_level_value = if run_level == "highest" {
+            windows::Win32::System::TaskScheduler::TASK_RUNLEVEL_HIGHEST
+        } else {
+            windows::Win32::System::TaskScheduler::TASK_RUNLEVEL_LUA
+        };
+        principal.SetRunLevel(run_level_value)?;
+
+        let settings = definition.Settings()?;
+        settings.SetMultipleInstances(
+            windows::Win32::System::TaskScheduler::TASK_INSTANCES_IGNORE_NEW,
+        )?;
+        settings.SetDisallowStartIfOnBatteries(false)?;
+        settings.SetStopIfGoingOnBatteries(false)?;
+        settings.SetEnabled(true)?;
+
+        let actions = definition.Actions()?;
+        let action = actions.Create(TASK_ACTION_EXEC)?;
+        let exec_action: IExecAction = action.cast()?;
+        exec_action.SetPath(&BSTR::from(program))?;
+        exec_action.SetWorkingDirectory(&BSTR::from(work_dir.as_str()))?;
+
+        add_triggers(&definition, triggers, verbose)?;
+
+        folder.RegisterTaskDefinition(
+            &BSTR::from(task_name),
+            &definition,
+            TASK_CREATE_OR_UPDATE.0,
+            &VARIANT::default(),
+            &VARIANT::default(),
+            TASK_LOGON_INTERACTIVE_TOKEN,
+            &VARIANT::default(),
+        )?;
+    }
 
-    let temp_xml_path = format!("temp_task_{}.xml", task_name);
-    fs::write(&temp_xml_path, xml_content)?;
+    println!("✅ 任务计划创建成功: {}", task_name);
 
-    if verbose {
-        println!("📄 已生成临时XML文件: {}", temp_xml_path);
+    Ok(())
+}
+
+/// 根据 `TriggerOptions` 向任务定义追加登录/每日/开机触发器，均不设置时保留空触发器集合
+#[cfg(target_os = "windows")]
+fn add_triggers(
+    definition: &windows::Win32::System::TaskScheduler::ITaskDefinition,
+    triggers: &TriggerOptions,
+    verbose: bool,
+) -> Result<(), Box<dyn Error>> {
+    if !triggers.logon && triggers.daily_at.is_none() && !triggers.boot {
+        return Ok(());
     }
 
-    let create_args = vec!["/Create", "/XML", &temp_xml_path, "/TN", task_name, "/F"];
+    let trigger_collection = unsafe { definition.Triggers()? };
 
-    let output = ProcessCommand::new("schtasks")
-        .args(&create_args)
-        .output()?;
+    if triggers.logon {
+        if verbose {
+            println!("⏰ 添加登录触发器");
+        }
+        unsafe {
+            let trigger = trigger_collection.Create(TASK_TRIGGER_LOGON)?;
+            let logon_trigger: ILogonTrigger = trigger.cast()?;
+            logon_trigger.SetStartBoundary(&BSTR::from(
+                Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            ))?;
+            if let Ok(sid) = get_current_user_sid() {
+                logon_trigger.SetUserId(&BSTR::from(sid))?;
+            }
+        }
+    }
 
-    let _ = fs::remove_file(&temp_xml_path);
-    if verbose {
-        println!("🗑️ 已删除临时XML文件: {}", temp_xml_path);
+    if let Some(time) = &triggers.daily_at {
+        if verbose {
+            println!("⏰ 添加每日触发器: {}", time);
+        }
+        let (hour, minute) = time
+            .split_once(':')
+            .ok_or("--trigger-daily 需要 HH:MM 格式")?;
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let start_boundary = format!("{}T{}:{}:00", today, hour, minute);
+
+        unsafe {
+            let trigger = trigger_collection.Create(TASK_TRIGGER_DAILY)?;
+            let daily_trigger: IDailyTrigger = trigger.cast()?;
+            daily_trigger.SetStartBoundary(&BSTR::from(start_boundary))?;
+            daily_trigger.SetDaysInterval(1)?;
+        }
     }
 
-    if output.status.success() {
-        println!("✅ 任务计划创建成功: {}", task_name);
+    if triggers.boot {
         if verbose {
-            let result = String::from_utf8_lossy(&output.stdout);
-            println!("📋 创建结果: {}", result);
+            println!("⏰ 添加开机触发器");
+        }
+        unsafe {
+            let trigger = trigger_collection.Create(TASK_TRIGGER_BOOT)?;
+            let boot_trigger: IBootTrigger = trigger.cast()?;
+            boot_trigger.SetStartBoundary(&BSTR::from(
+                Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            ))?;
         }
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("创建任务计划失败: {}", error).into());
     }
 
     Ok(())
 }
 
+/// 通过 LookupAccountNameW 获取当前登录用户的 SID 字符串，用于登录触发器的用户范围限定
+#[cfg(target_os = "windows")]
+fn get_current_user_sid() -> Result<String, Box<dyn Error>> {
+    use windows::Win32::System::WindowsProgramming::GetUserNameW;
+
+    let mut name_buf = [0u16; 256];
+    let mut name_len = name_buf.len() as u32;
+    unsafe {
+        GetUserNameW(PWSTR(name_buf.as_mut_ptr()), &mut name_len)
+            .ok()
+            .map_err(|_| format!("获取当前用户名失败: {:?}", GetLastError()))?;
+    }
+    let username = String::from_utf16_lossy(&name_buf[..(name_len as usize).saturating_sub(1)]);
+
+    let mut sid_buf = vec![0u8; 256];
+    let mut sid_size = sid_buf.len() as u32;
+    let mut domain_buf = [0u16; 256];
+    let mut domain_size = domain_buf.len() as u32;
+    let mut use_kind = SID_NAME_USE::default();
+
+    unsafe {
+        LookupAccountNameW(
+            None,
+            &BSTR::from(username.as_str()),
+            Some(windows::Win32::Security::PSID(sid_buf.as_mut_ptr() as _)),
+            &mut sid_size,
+            PWSTR(domain_buf.as_mut_ptr()),
+            &mut domain_size,
+            &mut use_kind,
+        )?;
+
+        let mut sid_string = PWSTR::null();
+        ConvertSidToStringSidW(
+            windows::Win32::Security::PSID(sid_buf.as_mut_ptr() as _),
+            &mut sid_string,
+        )?;
+        Ok(sid_string.to_string()?)
+    }
+}
+
 #[cfg(target_os = "windows")]
 fn delete_task(task_name: &str, verbose: bool) -> Result<(), Box<dyn Error>> {
     if verbose {
         println!("🗑️  删除任务计划: {}", task_name);
     }
 
-    let check_output = ProcessCommand::new("schtasks")
-        .args(&["/Query", "/TN", task_name])
-        .output()?;
+    let _com = ComGuard::new()?;
+    let (_service, folder) = connect_task_service()?;
 
-    if !check_output.status.success() {
+    if !task_exists_in_folder(&folder, task_name, false)? {
         println!("✅ 任务计划不存在: {}", task_name);
         return Ok(());
     }
 
-    let args = vec!["/Delete", "/TN", task_name, "/F"];
-    let output = ProcessCommand::new("schtasks").args(&args).output()?;
-
-    if output.status.success() {
-        println!("✅ 任务计划删除成功: {}", task_name);
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("删除任务计划失败: {}", error).into());
+    unsafe {
+        folder.DeleteTask(&BSTR::from(task_name), 0)?;
     }
 
+    println!("✅ 任务计划删除成功: {}", task_name);
+
     Ok(())
 }
 
 #[cfg(target_os = "windows")]
-fn list_tasks(verbose: bool) -> Result<(), Box<dyn Error>> {
+fn list_tasks(verbose: bool) -> Result<Vec<TaskInfo>, Box<dyn Error>> {
     if verbose {
         println!("📋 列出所有任务计划...");
     }
 
-    let output = ProcessCommand::new("schtasks")
-        .args(&["/Query", "/FO", "TABLE"])
-        .output()?;
-
-    if output.status.success() {
-        let tasks = String::from_utf8_lossy(&output.stdout);
-        println!("📋 任务计划列表:");
-        println!("{}", tasks);
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("获取任务列表失败: {}", error).into());
-    }
-
-    Ok(())
-}
-
-#[cfg(target_os = "windows")]
-fn generate_task_xml(
-    task_name: &str,
-    program: &str,
-    working_dir: &str,
-    description: &str,
-    run_level: &str,
-) -> Result<String, Box<dyn Error>> {
-    let current_time = Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
-    let run_level_value = if run_level == "highest" {
-        "HighestAvailable"
-    } else {
-        "LeastPrivilege"
-    };
-
-    let user_sid = get_current_user_sid().unwrap_or_else(|_| "S-1-5-32-544".to_string());
-
-    let xml_content = format!(
-        r#"<?xml version="1.0" encoding="UTF-16"?>
-<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
-  <RegistrationInfo>
-    <Date>{}</Date>
-    <Author>stranslate - zggsong</Author>
-    <Description>{}</Description>
-    <URI>\{}</URI>
-  </RegistrationInfo>
-  <Triggers />
-  <Principals>
-    <Principal id="Author">
-      <UserId>{}</UserId>
-      <LogonType>InteractiveToken</LogonType>
-      <RunLevel>{}</RunLevel>
-    </Principal>
-  </Principals>
-  <Settings>
-    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
-    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
-    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
-    <AllowHardTerminate>true</AllowHardTerminate>
-    <StartWhenAvailable>false</StartWhenAvailable>
-    <RunOnlyIfNetworkAvailable>false</RunOnlyIfNetworkAvailable>
-    <IdleSettings>
-      <StopOnIdleEnd>true</StopOnIdleEnd>
-      <RestartOnIdle>false</RestartOnIdle>
-    </IdleSettings>
-    <AllowStartOnDemand>true</AllowStartOnDemand>
-    <Enabled>true</Enabled>
-    <Hidden>false</Hidden>
-    <RunOnlyIfIdle>false</RunOnlyIfIdle>
-    <WakeToRun>false</WakeToRun>
-    <ExecutionTimeLimit>PT72H</ExecutionTimeLimit>
-    <Priority>4</Priority>
-  </Settings>
-  <Actions Context="Author">
-    <Exec>
-      <Command>{}</Command>
-      <WorkingDirectory>{}</WorkingDirectory>
-    </Exec>
-  </Actions>
-</Task>"#,
-        current_time, description, task_name, user_sid, run_level_value, program, working_dir
-    );
-
-    Ok(xml_content)
-}
-
-#[cfg(target_os = "windows")]
-fn get_current_user_sid() -> Result<String, Box<dyn Error>> {
-    let output = ProcessCommand::new("whoami")
-        .args(&["/user", "/fo", "csv", "/nh"])
-        .output()?;
-
-    if output.status.success() {
-        let result = String::from_utf8_lossy(&output.stdout);
-        if let Some(sid_part) = result.split(',').nth(1) {
-            let sid = sid_part.trim().trim_matches('"');
-            return Ok(sid.to_string());
+    let _com = ComGuard::new()?;
+    let (_service, folder) = connect_task_service()?;
+
+    unsafe {
+        let tasks = folder.GetTasks(0)?;
+        let count = tasks.Count()?;
+
+        let mut result = Vec::with_capacity(count as usize);
+        for i in 1..=count {
+            let task = tasks.Item(VARIANT::from(i))?;
+            let name = task.Name()?.to_string();
+            let enabled = task.Enabled()?.as_bool();
+            let state = format!("{:?}", task.State()?);
+
+            result.push(TaskInfo {
+                name,
+                enabled,
+                state,
+            });
         }
-    }
 
-    Err("无法获取当前用户SID".into())
+        Ok(result)
+    }
 }
@@ -1,32 +1,109 @@
 use clap::ArgMatches;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use std::error::Error;
 use std::fs;
-use std::io::{self};
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command as ProcessCommand;
 use std::thread;
 use std::time::Duration;
 use zip::read::ZipArchive;
 
+/// `update` 子命令的参数，供 CLI 解析与 `run --manifest` 的批处理步骤共用
+#[derive(Debug, Deserialize)]
+pub struct UpdateArgs {
+    pub archive: Option<String>,
+    pub url: Option<String>,
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub headers: Vec<String>,
+    #[serde(default)]
+    pub wait_time: u64,
+    #[serde(default)]
+    pub clean: bool,
+    pub process_name: Option<String>,
+    #[serde(default)]
+    pub auto_start: bool,
+    #[serde(default)]
+    pub no_rollback: bool,
+    #[serde(default)]
+    pub verbose: bool,
+}
+
 pub fn handle_update_command(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let archive_path = matches.get_one::<String>("archive").unwrap();
-    let wait_time = *matches.get_one::<u64>("wait-time").unwrap();
-    let should_clean = matches.get_flag("clean");
-    let process_name = matches.get_one::<String>("process-name");
-    let auto_start = matches.get_flag("auto-start");
-    let verbose = matches.get_flag("verbose");
+    let args = UpdateArgs {
+        archive: matches.get_one::<String>("archive").cloned(),
+        url: matches.get_one::<String>("url").cloned(),
+        sha256: matches.get_one::<String>("sha256").cloned(),
+        headers: matches
+            .get_many::<String>("headers")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        wait_time: *matches.get_one::<u64>("wait-time").unwrap(),
+        clean: matches.get_flag("clean"),
+        process_name: matches.get_one::<String>("process-name").cloned(),
+        auto_start: matches.get_flag("auto-start"),
+        no_rollback: matches.get_flag("no-rollback"),
+        verbose: matches.get_flag("verbose"),
+    };
+
+    run_update(&args)
+}
+
+pub fn run_update(args: &UpdateArgs) -> Result<(), Box<dyn Error>> {
+    let UpdateArgs {
+        archive: archive_arg,
+        url,
+        sha256,
+        headers,
+        wait_time,
+        clean: should_clean,
+        process_name,
+        auto_start,
+        no_rollback,
+        verbose,
+    } = args;
+    let headers: Vec<&String> = headers.iter().collect();
+    let wait_time = *wait_time;
+    let should_clean = *should_clean;
+    let auto_start = *auto_start;
+    let no_rollback = *no_rollback;
+    let verbose = *verbose;
 
     if verbose {
         println!("🔧 开始更新程序...");
-        println!("   压缩包路径: {}", archive_path);
+        if let Some(archive_path) = archive_arg {
+            println!("   压缩包路径: {}", archive_path);
+        }
+        if let Some(url) = url {
+            println!("   下载地址: {}", url);
+        }
         if wait_time > 0 {
             println!("   等待时间: {} 秒", wait_time);
         }
         println!("   清理目录: {}", should_clean);
         println!("   自动启动: {}", auto_start);
+        if should_clean {
+            println!("   失败自动回滚: {}", !no_rollback);
+        }
     }
 
-    if !Path::new(archive_path).exists() {
+    let (archive_path, downloaded) = match (archive_arg, url) {
+        (_, Some(url)) => {
+            // 下载到安装目录的 tmp 下，保持与 --archive 一致的 "zip 的祖父目录即安装目录" 约定
+            let install_dir = current_install_dir()?;
+            let temp_path =
+                download_update_package(url, &install_dir, &headers, sha256.as_ref(), verbose)?;
+            (temp_path, true)
+        }
+        (Some(archive_path), None) => (PathBuf::from(archive_path), false),
+        (None, None) => return Err("必须指定 --archive 或 --url 之一".into()),
+    };
+    let archive_path = archive_path.to_string_lossy().to_string();
+
+    if !downloaded && !Path::new(&archive_path).exists() {
         return Err(format!("压缩包不存在: {}", archive_path).into());
     }
 
@@ -44,35 +121,313 @@ pub fn handle_update_command(matches: &ArgMatches) -> Result<(), Box<dyn Error>>
         thread::sleep(Duration::from_secs(wait_time));
     }
 
-    unzip_file_to_parent_dir(archive_path, should_clean)?;
+    let result = run_extraction(&archive_path, should_clean, auto_start, no_rollback, verbose);
 
-    if verbose {
-        println!("✅ 解压完成");
+    if downloaded {
+        let _ = fs::remove_file(&archive_path);
+        if verbose {
+            println!("🗑️ 已删除临时下载文件: {}", archive_path);
+        }
     }
 
-    if auto_start {
-        let parent = Path::new(archive_path)
-            .parent()
-            .and_then(|p| p.parent())
-            .ok_or("无法确定程序目录")?;
+    result?;
+
+    println!("✅ 更新完成!");
+    Ok(())
+}
+
+fn run_extraction(
+    archive_path: &str,
+    should_clean: bool,
+    auto_start: bool,
+    no_rollback: bool,
+    verbose: bool,
+) -> Result<(), Box<dyn Error>> {
+    let transactional = should_clean && !no_rollback;
 
-        let exe_path = parent.join("STranslate.exe");
+    let grand_parent_dir = Path::new(archive_path)
+        .parent()
+        .and_then(|p| p.parent())
+        .ok_or("无法确定程序目录")?
+        .to_path_buf();
+
+    let backup_dir = if transactional {
+        if verbose {
+            println!("🗄️  正在备份当前安装目录...");
+        }
+        Some(backup_install_dir(&grand_parent_dir)?)
+    } else {
+        None
+    };
+
+    let attempt = (|| -> Result<(), Box<dyn Error>> {
+        // 事务模式下备份已转移走旧文件，解压前无需再清理
+        unzip_file_to_parent_dir(archive_path, should_clean && !transactional)?;
+
+        if verbose {
+            println!("✅ 解压完成");
+        }
+
+        if auto_start {
+            let exe_path = grand_parent_dir.join("STranslate.exe");
+
+            if !exe_path.exists() {
+                return Err("解压后未找到 STranslate.exe，自动启动校验失败".into());
+            }
 
-        if exe_path.exists() {
             if verbose {
                 println!("🚀 启动 STranslate.exe...");
             }
             std::process::Command::new(&exe_path).spawn()?;
             println!("✅ 程序已启动");
-        } else if verbose {
-            println!("⚠️  STranslate.exe 不存在，跳过自动启动");
         }
+
+        Ok(())
+    })();
+
+    if let Some(backup_dir) = backup_dir {
+        match attempt {
+            Ok(()) => {
+                fs::remove_dir_all(&backup_dir)?;
+                if verbose {
+                    println!("🗑️ 已清理备份目录: {}", backup_dir.display());
+                }
+                Ok(())
+            }
+            Err(err) => {
+                if verbose {
+                    println!("⚠️  更新失败，正在回滚: {}", err);
+                }
+                restore_backup(&grand_parent_dir, &backup_dir)?;
+                Err(format!("更新失败，已回滚到更新前状态: {}", err).into())
+            }
+        }
+    } else {
+        attempt
     }
+}
+
+const UPDATE_SKIP_DIRS: [&str; 3] = ["log", "portable_config", "tmp"];
+
+/// 将安装目录中的非白名单内容整体移动到 `tmp/backup-<ts>`，供更新失败时回滚
+fn backup_install_dir(install_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let backup_dir = install_dir.join("tmp").join(format!("backup-{}", timestamp));
+    fs::create_dir_all(&backup_dir)?;
+
+    if let Ok(entries) = fs::read_dir(install_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+            if UPDATE_SKIP_DIRS.contains(&name) {
+                continue;
+            }
+
+            fs::rename(&path, backup_dir.join(name))?;
+        }
+    }
+
+    Ok(backup_dir)
+}
+
+/// 将备份目录的内容移回安装目录，撤销一次失败的事务性更新
+fn restore_backup(install_dir: &Path, backup_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut backed_up_names = std::collections::HashSet::new();
+
+    if let Ok(entries) = fs::read_dir(backup_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            backed_up_names.insert(name.to_string());
+            let dest = install_dir.join(name);
+
+            if dest.is_dir() {
+                fs::remove_dir_all(&dest)?;
+            } else if dest.is_file() {
+                fs::remove_file(&dest)?;
+            }
+
+            fs::rename(&path, &dest)?;
+        }
+    }
+
+    // 部分解压可能新增了一些备份里没有的条目（新版本独有的文件/目录），
+    // 这些不属于更新前的状态，需要一并清除，否则回滚并不完整
+    if let Ok(entries) = fs::read_dir(install_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+            if UPDATE_SKIP_DIRS.contains(&name) || backed_up_names.contains(name) {
+                continue;
+            }
+
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    fs::remove_dir_all(backup_dir)?;
 
-    println!("✅ 更新完成!");
     Ok(())
 }
 
+/// 确定当前安装目录：取宿主可执行文件所在的目录
+fn current_install_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let exe_path = std::env::current_exe()?;
+    exe_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .ok_or_else(|| "无法确定程序所在目录".into())
+}
+
+/// 下载更新包到安装目录下的 `tmp` 文件夹，边下载边校验 SHA-256，成功后返回临时文件路径
+///
+/// 文件名由 URL 的哈希派生（而非进程号），这样中断后重新运行同一条命令能找到上次
+/// 写入的字节并以 `Range` 续传，而不是每次都从零重新下载。续传只在提供了
+/// `--sha256` 时进行；未提供时已有的分片会被丢弃并从零重新下载。
+fn download_update_package(
+    url: &str,
+    install_dir: &Path,
+    headers: &[&String],
+    expected_sha256: Option<&String>,
+    verbose: bool,
+) -> Result<PathBuf, Box<dyn Error>> {
+    // 放在安装目录的 tmp 下，而不是 OS 临时目录，这样 unzip_file_to_parent_dir
+    // 按 "zip 的祖父目录即安装目录" 推断出的目标目录才是正确的
+    let temp_dir = install_dir.join("tmp");
+    fs::create_dir_all(&temp_dir)?;
+    let url_hash = hex_encode(&Sha256::digest(url.as_bytes()));
+    let file_name = format!("stranslate-update-{}.zip", &url_hash[..16]);
+    let temp_path = temp_dir.join(file_name);
+
+    let mut existing_len = fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
+
+    // 没有 --sha256 时无法校验本地分片是否仍对应当前资源内容（例如 URL 指向会变化的
+    // "latest" 链接），续传可能把新内容接到旧内容后面拼出一个损坏的包，因此直接丢弃重来
+    if existing_len > 0 && expected_sha256.is_none() {
+        if verbose {
+            println!(
+                "⚠️  未提供 --sha256，无法校验历史分片，已丢弃并重新下载: {}",
+                temp_path.display()
+            );
+        }
+        let _ = fs::remove_file(&temp_path);
+        existing_len = 0;
+    }
+
+    if verbose {
+        if existing_len > 0 {
+            println!(
+                "⬇️  正在续传更新包: {} (已下载 {} 字节)",
+                url, existing_len
+            );
+        } else {
+            println!("⬇️  正在下载更新包: {}", url);
+        }
+    }
+
+    let client = reqwest::blocking::Client::new();
+    let mut response = send_download_request(&client, url, headers, existing_len, verbose)?;
+
+    // 本地分片其实已经完整（或服务器状态已变化）时，Range 请求会收到 416，
+    // 丢弃分片后完整重新下载一次，而不是让每次调用都卡在同一个 416 上
+    if existing_len > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        if verbose {
+            println!(
+                "⚠️  续传请求返回 416，丢弃本地分片重新下载: {}",
+                temp_path.display()
+            );
+        }
+        let _ = fs::remove_file(&temp_path);
+        existing_len = 0;
+        response = send_download_request(&client, url, headers, existing_len, verbose)?;
+    }
+
+    // 服务器忽略 Range 时返回 200（完整内容），此时必须从头覆盖，否则数据会重复
+    let resumed = existing_len > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut response = response.error_for_status()?;
+
+    let mut hasher = Sha256::new();
+    let mut temp_file = if resumed {
+        let mut existing = fs::File::open(&temp_path)?;
+        io::copy(&mut existing, &mut hasher)?;
+        fs::OpenOptions::new().append(true).open(&temp_path)?
+    } else {
+        fs::File::create(&temp_path)?
+    };
+
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = io::Read::read(&mut response, &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        temp_file.write_all(&buffer[..read])?;
+    }
+    temp_file.flush()?;
+
+    if let Some(expected) = expected_sha256 {
+        let digest = hex_encode(&hasher.finalize());
+        if !digest.eq_ignore_ascii_case(expected) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(format!(
+                "更新包校验失败: 期望 sha256 {}，实际为 {}",
+                expected, digest
+            )
+            .into());
+        }
+        if verbose {
+            println!("✅ SHA-256 校验通过: {}", digest);
+        }
+    }
+
+    if verbose {
+        println!("✅ 下载完成: {}", temp_path.display());
+    }
+
+    Ok(temp_path)
+}
+
+/// 发起下载请求，`resume_from > 0` 时附加 `Range` 头；不在此处调用 `error_for_status`，
+/// 交由调用方先检查 416 等需要特殊处理的状态码
+fn send_download_request(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    headers: &[&String],
+    resume_from: u64,
+    verbose: bool,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let mut request = client.get(url);
+
+    for header in headers {
+        if let Some((key, value)) = header.split_once(':') {
+            request = request.header(key.trim(), value.trim());
+        } else if verbose {
+            println!("⚠️  忽略格式不正确的 header: {}", header);
+        }
+    }
+
+    if resume_from > 0 {
+        request = request.header("Range", format!("bytes={}-", resume_from));
+    }
+
+    request.send()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 /// 解压缩打包内容到父目录，可选择清理保留白名单之外的文件夹
 fn unzip_file_to_parent_dir(zip_path: &str, clear_dir: bool) -> io::Result<()> {
     let zip_path = Path::new(zip_path);
@@ -95,14 +450,12 @@ fn unzip_file_to_parent_dir(zip_path: &str, clear_dir: bool) -> io::Result<()> {
     };
 
     if clear_dir {
-        let skip_dirs = ["log", "portable_config", "tmp"];
-
         if let Ok(entries) = fs::read_dir(grand_parent_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
                 let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
-                if skip_dirs.contains(&name) {
+                if UPDATE_SKIP_DIRS.contains(&name) {
                     continue;
                 }
 
@@ -115,29 +468,89 @@ fn unzip_file_to_parent_dir(zip_path: &str, clear_dir: bool) -> io::Result<()> {
         }
     }
 
+    let canonical_root = fs::canonicalize(grand_parent_dir)?;
+
     let file = fs::File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
-        let outpath = grand_parent_dir.join(file.name());
+        let entry_name = file.name();
 
-        if file.name().ends_with('/') {
+        let relative_path = match sanitize_zip_entry_path(entry_name) {
+            Some(path) => path,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("压缩包条目路径不安全，已拒绝解压: {}", entry_name),
+                ));
+            }
+        };
+
+        let outpath = grand_parent_dir.join(&relative_path);
+
+        if entry_name.ends_with('/') {
             fs::create_dir_all(&outpath)?;
-        } else {
-            if let Some(p) = outpath.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(p)?;
-                }
+            ensure_within_root(&outpath, &canonical_root)?;
+            continue;
+        }
+
+        if let Some(p) = outpath.parent() {
+            if !p.exists() {
+                fs::create_dir_all(p)?;
+            }
+            ensure_within_root(p, &canonical_root)?;
+        }
+
+        let mut outfile = fs::File::create(&outpath)?;
+        io::copy(&mut file, &mut outfile)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
             }
-            let mut outfile = fs::File::create(&outpath)?;
-            io::copy(&mut file, &mut outfile)?;
         }
     }
 
     Ok(())
 }
 
+/// 校验压缩包条目名是否安全：拒绝绝对路径及包含 `..` 的条目，返回规范化后的相对路径
+fn sanitize_zip_entry_path(entry_name: &str) -> Option<PathBuf> {
+    let entry_path = Path::new(entry_name);
+
+    if entry_path.is_absolute() {
+        return None;
+    }
+
+    let mut sanitized = PathBuf::new();
+    for component in entry_path.components() {
+        match component {
+            std::path::Component::Normal(part) => sanitized.push(part),
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir
+            | std::path::Component::RootDir
+            | std::path::Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(sanitized)
+}
+
+/// 确认解压出的路径仍在 `root` 目录之内，防御 Zip-Slip 路径穿越
+fn ensure_within_root(path: &Path, root: &Path) -> io::Result<()> {
+    let canonical = fs::canonicalize(path)?;
+    if !canonical.starts_with(root) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("解压路径超出目标目录，已拒绝: {}", canonical.display()),
+        ));
+    }
+    Ok(())
+}
+
 fn close_process(process_name: &str, verbose: bool) -> Result<(), Box<dyn Error>> {
     if verbose {
         println!("🔄 正在关闭进程: {}", process_name);
@@ -161,3 +574,67 @@ fn close_process(process_name: &str, verbose: bool) -> Result<(), Box<dyn Error>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_zip_entry_path_accepts_normal_nested_entries() {
+        assert_eq!(
+            sanitize_zip_entry_path("a/b/c.txt"),
+            Some(PathBuf::from("a").join("b").join("c.txt"))
+        );
+        assert_eq!(
+            sanitize_zip_entry_path("./a.txt"),
+            Some(PathBuf::from("a.txt"))
+        );
+    }
+
+    #[test]
+    fn sanitize_zip_entry_path_rejects_absolute_paths() {
+        assert_eq!(sanitize_zip_entry_path("/etc/passwd"), None);
+    }
+
+    #[test]
+    fn sanitize_zip_entry_path_rejects_parent_dir_components() {
+        assert_eq!(sanitize_zip_entry_path("../../evil.dll"), None);
+        assert_eq!(sanitize_zip_entry_path("a/../../b"), None);
+    }
+
+    /// 创建一个供测试使用的唯一临时目录，调用方负责在用完后删除
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "stranslate-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::canonicalize(&dir).unwrap()
+    }
+
+    #[test]
+    fn ensure_within_root_accepts_nested_path() {
+        let root = make_temp_dir("within-root-ok");
+        let inside = root.join("inside.txt");
+        fs::write(&inside, b"ok").unwrap();
+
+        assert!(ensure_within_root(&inside, &root).is_ok());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ensure_within_root_rejects_path_outside_root() {
+        let root = make_temp_dir("within-root-guard");
+        let outside = make_temp_dir("within-root-escape");
+        let escapee = outside.join("evil.dll");
+        fs::write(&escapee, b"evil").unwrap();
+
+        assert!(ensure_within_root(&escapee, &root).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+mod commands;
+
+use clap::{Arg, ArgAction, Command, value_parser};
+use commands::{
+    StartMode, TaskAction, handle_run_command, handle_start_command, handle_task_command,
+    handle_update_command,
+};
+use std::process::ExitCode;
+
+fn verbose_arg() -> Arg {
+    Arg::new("verbose")
+        .long("verbose")
+        .short('v')
+        .help("输出详细执行信息")
+        .action(ArgAction::SetTrue)
+}
+
+fn start_command() -> Command {
+    Command::new("start")
+        .about("启动 STranslate 主程序")
+        .arg(
+            Arg::new("mode")
+                .long("mode")
+                .required(true)
+                .value_parser(value_parser!(StartMode)),
+        )
+        .arg(Arg::new("target").long("target").required(true))
+        .arg(Arg::new("args").long("args").action(ArgAction::Append))
+        .arg(
+            Arg::new("delay")
+                .long("delay")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(verbose_arg())
+}
+
+fn update_command() -> Command {
+    Command::new("update")
+        .about("应用一次程序更新")
+        .arg(Arg::new("archive").long("archive"))
+        .arg(Arg::new("url").long("url"))
+        .arg(Arg::new("sha256").long("sha256"))
+        .arg(Arg::new("headers").long("headers").action(ArgAction::Append))
+        .arg(
+            Arg::new("wait-time")
+                .long("wait-time")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(Arg::new("clean").long("clean").action(ArgAction::SetTrue))
+        .arg(Arg::new("process-name").long("process-name"))
+        .arg(
+            Arg::new("auto-start")
+                .long("auto-start")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-rollback")
+                .long("no-rollback")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(verbose_arg())
+}
+
+fn task_command() -> Command {
+    Command::new("task")
+        .about("管理 Windows 计划任务")
+        .arg(
+            Arg::new("action")
+                .long("action")
+                .required(true)
+                .value_parser(value_parser!(TaskAction)),
+        )
+        .arg(Arg::new("name").long("name").required(true))
+        .arg(Arg::new("program").long("program"))
+        .arg(Arg::new("working-dir").long("working-dir"))
+        .arg(Arg::new("description").long("description").default_value(""))
+        .arg(Arg::new("run-level").long("run-level").default_value("limited"))
+        .arg(Arg::new("force").long("force").action(ArgAction::SetTrue))
+        .arg(
+            Arg::new("trigger-logon")
+                .long("trigger-logon")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(Arg::new("trigger-daily").long("trigger-daily"))
+        .arg(
+            Arg::new("trigger-boot")
+                .long("trigger-boot")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(verbose_arg())
+}
+
+fn run_command() -> Command {
+    Command::new("run")
+        .about("按清单批量执行 start/update/task 步骤")
+        .arg(Arg::new("manifest").long("manifest").required(true))
+        .arg(verbose_arg())
+}
+
+fn build_cli() -> Command {
+    Command::new("STranslate.Host")
+        .about("STranslate 的启动/更新/任务辅助进程")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(start_command())
+        .subcommand(update_command())
+        .subcommand(task_command())
+        .subcommand(run_command())
+}
+
+fn main() -> ExitCode {
+    let matches = build_cli().get_matches();
+
+    let result = match matches.subcommand() {
+        Some(("start", sub_matches)) => handle_start_command(sub_matches),
+        Some(("update", sub_matches)) => handle_update_command(sub_matches),
+        Some(("task", sub_matches)) => handle_task_command(sub_matches),
+        Some(("run", sub_matches)) => handle_run_command(sub_matches),
+        _ => unreachable!("clap 已要求必须指定子命令"),
+    };
+
+    if let Err(err) = result {
+        eprintln!("❌ {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}